@@ -4,10 +4,19 @@ use super::error::OverviewerError;
 use std::path::{PathBuf, Path};
 use std::convert::From;
 use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::collections::HashSet;
+use std::ops::Add;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use super::coords;
 use super::coords::Coord;
 
+/// Number of bytes in a single region file sector.
+const SECTOR_BYTES: u64 = 4096;
+/// Number of chunk slots in a region file's location/timestamp tables.
+const CHUNKS_PER_REGION: usize = 1024;
+
 /// Encapsulates the concept of a Minecraft "world". A Minecraft world is a
 /// level.dat file, a players directory with info about each player, a data
 /// directory with info about that world's maps, and one or more "dimension"
@@ -53,24 +62,170 @@ impl World {
         })
     }
 
+    /// Returns an iterator over the regionsets already discovered for this
+    /// world (see `World::new`). This does not re-scan the world directory.
     pub fn get_regionsets(&self) -> RegionsetIter {
-        unimplemented!()
+        RegionsetIter(self.regionsets.clone().into_iter())
     }
 
+    /// Returns a handle to the regionset at `idx`, cloned from the list
+    /// discovered when this `World` was created.
     pub fn get_regionset(&self, idx: usize) -> Regionset {
-        unimplemented!()
+        self.regionsets[idx].clone()
+    }
+
+    /// Scans every regionset in this world for corrupted or suspicious
+    /// chunks, returning aggregate `ScanStatistics` across all of them.
+    pub fn scan(&self) -> ScanStatistics {
+        let mut stats = ScanStatistics::default();
+        for regionset in &self.regionsets {
+            stats.merge(&regionset.scan());
+        }
+        stats
+    }
+
+    /// Like `scan`, but fans the work for every region file across this
+    /// world's regionsets out over a rayon thread pool sized to `threads`.
+    /// `progress`, if given, is called after each region file finishes with
+    /// `(files_done, files_total)`.
+    pub fn scan_parallel(&self, threads: usize, progress: Option<ProgressFn>) -> ScanStatistics {
+        use rayon::prelude::*;
+
+        let pool = match ::rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => pool,
+            Err(_) => return self.scan(),
+        };
+
+        let tasks: Vec<(&Regionset, i64, i64)> = self.regionsets
+            .iter()
+            .flat_map(|rs| rs.regions.iter().map(move |&(x, z)| (rs, x, z)))
+            .collect();
+        let total = tasks.len();
+        let done = AtomicUsize::new(0);
+
+        pool.install(|| {
+            tasks.par_iter()
+                .map(|&(rs, rx, rz)| {
+                    let mut stats = ScanStatistics::default();
+                    rs.scan_region(rx, rz, &mut stats);
+                    let finished = done.fetch_add(1, Ordering::SeqCst) + 1;
+                    if let Some(progress) = progress {
+                        progress(finished, total);
+                    }
+                    stats
+                })
+                .reduce(ScanStatistics::default, Add::add)
+        })
+    }
+
+    /// Returns this world's spawn point as `(x, y, z)`, read from
+    /// `Data.SpawnX/SpawnY/SpawnZ` in level.dat. Returns `None` rather than
+    /// panicking if any of those tags are missing or of the wrong type.
+    pub fn spawn(&self) -> Option<(i32, i32, i32)> {
+        use nbtrs::Taglike;
+
+        let data = self.level_dat.key("Data");
+        let x = data.key("SpawnX").as_i32();
+        let y = data.key("SpawnY").as_i32();
+        let z = data.key("SpawnZ").as_i32();
+        match (x, y, z) {
+            (Ok(x), Ok(y), Ok(z)) => Some((x, y, z)),
+            _ => None,
+        }
+    }
+
+    /// Returns this world's display name, read from `Data.LevelName`.
+    pub fn level_name(&self) -> Option<String> {
+        use nbtrs::Taglike;
+
+        self.level_dat.key("Data").key("LevelName").as_string().ok().map(|s| s.to_string())
+    }
+
+    /// Returns the Minecraft data version this world was last saved with,
+    /// read from `Data.DataVersion`. Absent on worlds saved before
+    /// Minecraft 1.9.
+    pub fn data_version(&self) -> Option<i32> {
+        use nbtrs::Taglike;
+
+        self.level_dat.key("Data").key("DataVersion").as_i32().ok()
+    }
+
+    /// Returns this world's random seed, read from `Data.RandomSeed`.
+    pub fn seed(&self) -> Option<i64> {
+        use nbtrs::Taglike;
+
+        self.level_dat.key("Data").key("RandomSeed").as_i64().ok()
     }
 }
 
-pub struct RegionsetIter;
+pub struct RegionsetIter(::std::vec::IntoIter<Regionset>);
 
 impl Iterator for RegionsetIter {
     type Item = Regionset;
     fn next(&mut self) -> Option<Regionset> {
-        unimplemented!()
+        self.0.next()
+    }
+}
+
+
+/// Maximum on-disk length (in bytes, including the 1-byte compression tag)
+/// Overviewer will accept for a single chunk's payload, matching the 128
+/// sector cap the Minecraft region format itself imposes.
+const MAX_CHUNK_LENGTH: u32 = 128 * 4096;
+
+/// Aggregate counts produced by `Regionset::scan`/`World::scan`, classifying
+/// every chunk slot found across a world's region files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScanStatistics {
+    /// Chunks with a sane location entry and valid, well-formed NBT.
+    pub ok: u64,
+    /// Location entries pointing outside the file or into the header sectors.
+    pub bad_offset: u64,
+    /// Location entries whose sector ranges overlap another chunk's.
+    pub overlapping: u64,
+    /// Chunks whose declared payload length is zero or absurdly large.
+    pub bad_length: u64,
+    /// Chunks whose compression id byte isn't gzip/zlib/uncompressed, or
+    /// whose payload fails to decompress under the declared method.
+    pub bad_compression: u64,
+    /// Chunks that decompressed fine but failed NBT-level validation.
+    pub bad_nbt: u64,
+}
+
+impl ScanStatistics {
+    /// Folds another regionset/region file's statistics into this one.
+    fn merge(&mut self, other: &ScanStatistics) {
+        self.ok += other.ok;
+        self.bad_offset += other.bad_offset;
+        self.overlapping += other.overlapping;
+        self.bad_length += other.bad_length;
+        self.bad_compression += other.bad_compression;
+        self.bad_nbt += other.bad_nbt;
+    }
+}
+
+/// Lets per-region-file `ScanStatistics` be combined with a plain `+`, which
+/// is what makes them reducible across a rayon parallel scan.
+impl Add for ScanStatistics {
+    type Output = ScanStatistics;
+    fn add(self, other: ScanStatistics) -> ScanStatistics {
+        let mut sum = self;
+        sum.merge(&other);
+        sum
     }
 }
 
+/// Callback invoked during a parallel scan with `(items_done, items_total)`,
+/// where an item is a single region file.
+pub type ProgressFn<'a> = &'a (Fn(usize, usize) + Sync);
+
+/// Outcome of validating a single chunk's payload during a scan.
+enum ChunkScanResult {
+    Ok,
+    BadLength,
+    BadCompression,
+    BadNbt,
+}
 
 /// This object is the gateway to a particular Minecraft dimension within a
 /// world. It corresponds to a set of region files containing the actual
@@ -79,12 +234,16 @@ impl Iterator for RegionsetIter {
 ///
 /// See the docs for the World object for more information on the difference
 /// between Worlds and RegionSets.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Regionset {
     region_dir: PathBuf,
 
     // A vec of regions might be too memory intensive, so hold a list of regions by coords
     regions: Vec<(i64, i64)>,
+
+    // When set, get_chunk will attempt to repair chunks whose compression
+    // id byte doesn't match their actual payload (see `with_fix_compression`).
+    fix_compression: bool,
 }
 impl Regionset {
     /// Given a folder of MCA files, create a RegionSet
@@ -113,10 +272,21 @@ impl Regionset {
         Ok(Regionset {
             region_dir: region_dir.to_owned(),
             regions: regions,
+            fix_compression: false,
         })
 
     }
 
+    /// Opts this regionset into repairing chunks whose declared compression
+    /// id byte doesn't match their actual payload: `get_chunk` will try the
+    /// other compression methods and, if exactly one of them decompresses
+    /// into parseable NBT with an integer `Level.xPos`, rewrite the
+    /// compression byte in place.
+    pub fn with_fix_compression(mut self, enabled: bool) -> Regionset {
+        self.fix_compression = enabled;
+        self
+    }
+
     pub fn get_type(&self) -> String {
         unimplemented!()
     }
@@ -127,8 +297,9 @@ impl Regionset {
         if !self.regions.contains(&(r.x, r.z)) {
             return None;
         }
-        let f = self.region_dir.join(format!("r.{}.{}.mca", r.x, r.z));
-        if let Ok(f) = File::open(f) {
+        let path = self.region_dir.join(format!("r.{}.{}.mca", r.x, r.z));
+
+        if let Ok(f) = File::open(&path) {
             if let Ok(mut region_file) = RegionFile::new(f) {
                 if let Ok(chunk) = region_file.load_chunk(c.x as u8, c.z as u8) {
                     return Some(Chunk(chunk));
@@ -136,14 +307,108 @@ impl Regionset {
             }
         }
 
+        // The chunk failed to load normally. If we're allowed to, see
+        // whether it's merely tagged with the wrong compression method and,
+        // if so, fix it up and retry.
+        if self.fix_compression && self.repair_chunk_compression(&path, c.x as u8, c.z as u8) {
+            if let Ok(f) = File::open(&path) {
+                if let Ok(mut region_file) = RegionFile::new(f) {
+                    if let Ok(chunk) = region_file.load_chunk(c.x as u8, c.z as u8) {
+                        return Some(Chunk(chunk));
+                    }
+                }
+            }
+        }
+
         None
     }
 
+    /// Attempts to repair a chunk whose declared compression id byte doesn't
+    /// match its payload. Tries every compression method (gzip, zlib,
+    /// uncompressed) other than the declared one against the raw bytes
+    /// already on disk; if exactly one of them decompresses into NBT that
+    /// parses and has an integer `Level.xPos`, rewrites the compression byte
+    /// in place and returns `true`.
+    fn repair_chunk_compression(&self, path: &Path, local_x: u8, local_z: u8) -> bool {
+        use std::fs::OpenOptions;
+        use std::io::Write;
+        use nbtrs::Taglike;
+
+        let idx = local_z as usize * 32 + local_x as usize;
+
+        let mut f = match OpenOptions::new().read(true).write(true).open(path) {
+            Ok(f) => f,
+            Err(_) => return false,
+        };
+
+        let mut header = vec![0u8; 2 * SECTOR_BYTES as usize];
+        if f.read_exact(&mut header).is_err() {
+            return false;
+        }
+
+        let entry = &header[idx * 4..idx * 4 + 4];
+        let offset = ((entry[0] as u32) << 16) | ((entry[1] as u32) << 8) | (entry[2] as u32);
+        let sector_count = entry[3];
+        if offset == 0 || sector_count == 0 {
+            return false;
+        }
+
+        let chunk_start = offset as u64 * SECTOR_BYTES;
+        if f.seek(SeekFrom::Start(chunk_start)).is_err() {
+            return false;
+        }
+        let mut len_buf = [0u8; 4];
+        if f.read_exact(&mut len_buf).is_err() {
+            return false;
+        }
+        let length = ((len_buf[0] as u32) << 24) | ((len_buf[1] as u32) << 16) |
+                     ((len_buf[2] as u32) << 8) | (len_buf[3] as u32);
+        if length == 0 || length > MAX_CHUNK_LENGTH {
+            return false;
+        }
+
+        let mut declared = [0u8; 1];
+        if f.read_exact(&mut declared).is_err() {
+            return false;
+        }
+        let mut payload = vec![0u8; length as usize - 1];
+        if f.read_exact(&mut payload).is_err() {
+            return false;
+        }
+
+        let mut working = Vec::new();
+        for &candidate in &[1u8, 2, 3] {
+            if candidate == declared[0] {
+                continue;
+            }
+            if let Some(bytes) = decompress(candidate, &payload) {
+                if let Ok((_, root)) = Tag::parse(&mut &bytes[..]) {
+                    if root.key("Level").key("xPos").as_i32().is_ok() {
+                        working.push(candidate);
+                    }
+                }
+            }
+        }
+
+        if working.len() != 1 {
+            return false;
+        }
+
+        match f.seek(SeekFrom::Start(chunk_start + 4)) {
+            Ok(_) => f.write_all(&[working[0]]).is_ok(),
+            Err(_) => false,
+        }
+    }
+
     /// Returns an iterator over all chunk metadata in this world. Iterates
     /// over tuples of integers (x,z,mtime) for each chunk.  Other chunk data
     /// is not returned here.
     pub fn get_chunks(&self) -> ChunkIter {
-        unimplemented!()
+        ChunkIter {
+            region_dir: self.region_dir.clone(),
+            regions: self.regions.clone().into_iter(),
+            current: None,
+        }
     }
 
     // TODO consider using something other than a u32 for time (like bring in one of the types from
@@ -163,16 +428,469 @@ impl Regionset {
 
         None
     }
+
+    /// Scans every region file in this regionset, classifying each occupied
+    /// chunk slot as valid or corrupt in one of several ways (see
+    /// `ScanStatistics`).
+    pub fn scan(&self) -> ScanStatistics {
+        let mut stats = ScanStatistics::default();
+        for &(rx, rz) in &self.regions {
+            self.scan_region(rx, rz, &mut stats);
+        }
+        stats
+    }
+
+    /// Like `scan`, but processes this regionset's region files concurrently
+    /// over a rayon thread pool sized to `threads`. `progress`, if given, is
+    /// called after each region file finishes with `(files_done, files_total)`.
+    pub fn scan_parallel(&self, threads: usize, progress: Option<ProgressFn>) -> ScanStatistics {
+        use rayon::prelude::*;
+
+        let pool = match ::rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => pool,
+            Err(_) => return self.scan(),
+        };
+
+        let total = self.regions.len();
+        let done = AtomicUsize::new(0);
+
+        pool.install(|| {
+            self.regions
+                .par_iter()
+                .map(|&(rx, rz)| {
+                    let mut stats = ScanStatistics::default();
+                    self.scan_region(rx, rz, &mut stats);
+                    let finished = done.fetch_add(1, Ordering::SeqCst) + 1;
+                    if let Some(progress) = progress {
+                        progress(finished, total);
+                    }
+                    stats
+                })
+                .reduce(ScanStatistics::default, Add::add)
+        })
+    }
+
+    /// Like `get_chunks`, but walks this regionset's region files
+    /// concurrently over a rayon thread pool sized to `threads`. Since
+    /// chunks never cross region file boundaries, this is embarrassingly
+    /// parallel: each file is opened and read independently.
+    pub fn par_chunks(&self, threads: usize) -> Vec<(i64, i64, u32)> {
+        use rayon::prelude::*;
+
+        let pool = match ::rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => pool,
+            Err(_) => return self.get_chunks().collect(),
+        };
+
+        pool.install(|| {
+            self.regions
+                .par_iter()
+                .flat_map(|&(x, z)| {
+                    RegionLocationIter::open(&self.region_dir, x, z)
+                        .map(|iter| iter.collect::<Vec<_>>())
+                        .unwrap_or_else(Vec::new)
+                })
+                .collect()
+        })
+    }
+
+    fn scan_region(&self, rx: i64, rz: i64, stats: &mut ScanStatistics) {
+        let path = self.region_dir.join(format!("r.{}.{}.mca", rx, rz));
+        let mut f = match File::open(&path) {
+            Ok(f) => f,
+            Err(_) => return,
+        };
+        let file_len = match f.metadata() {
+            Ok(m) => m.len(),
+            Err(_) => return,
+        };
+        let mut header = vec![0u8; 2 * SECTOR_BYTES as usize];
+        if f.read_exact(&mut header).is_err() {
+            return;
+        }
+        let total_sectors = (file_len + SECTOR_BYTES - 1) / SECTOR_BYTES;
+
+        // Gather the occupied location entries, flagging out-of-bounds ones
+        // immediately so they don't also get checked for overlap.
+        let mut entries = Vec::new();
+        for i in 0..CHUNKS_PER_REGION {
+            let entry = &header[i * 4..i * 4 + 4];
+            let offset = ((entry[0] as u32) << 16) | ((entry[1] as u32) << 8) | (entry[2] as u32);
+            let sector_count = entry[3];
+            if offset == 0 && sector_count == 0 {
+                continue;
+            }
+            if offset < 2 || (offset as u64 + sector_count as u64) > total_sectors {
+                stats.bad_offset += 1;
+                continue;
+            }
+            entries.push((offset, sector_count, i));
+        }
+
+        let overlapping = Regionset::find_overlapping(&entries);
+        stats.overlapping += overlapping.len() as u64;
+
+        for &(offset, _sector_count, idx) in &entries {
+            if overlapping.contains(&idx) {
+                continue;
+            }
+            let local_x = (idx % 32) as i64;
+            let local_z = (idx / 32) as i64;
+            let expected_x = rx * 32 + local_x;
+            let expected_z = rz * 32 + local_z;
+            match Regionset::scan_chunk(&mut f, offset, expected_x, expected_z) {
+                ChunkScanResult::Ok => stats.ok += 1,
+                ChunkScanResult::BadLength => stats.bad_length += 1,
+                ChunkScanResult::BadCompression => stats.bad_compression += 1,
+                ChunkScanResult::BadNbt => stats.bad_nbt += 1,
+            }
+        }
+    }
+
+    /// Returns the set of location-table indices whose sector ranges overlap
+    /// another entry's.
+    fn find_overlapping(entries: &[(u32, u8, usize)]) -> HashSet<usize> {
+        let mut by_offset = entries.to_vec();
+        by_offset.sort_by_key(|&(offset, _, _)| offset);
+
+        // Classic interval-merge sweep: track the furthest sector end seen
+        // so far, rather than only comparing each entry to its immediate
+        // neighbor. An entry that starts before that running end overlaps
+        // *something* already swept, even if it's not the previous entry
+        // (e.g. a short middle entry can end before the next one starts
+        // while both still sit inside an earlier, longer entry's range).
+        let mut overlapping = HashSet::new();
+        let mut max_end: u64 = 0;
+        let mut max_end_idx: usize = 0;
+        for &(offset, count, idx) in &by_offset {
+            let start = offset as u64;
+            let end = start + count as u64;
+            if max_end > start {
+                overlapping.insert(idx);
+                overlapping.insert(max_end_idx);
+            }
+            if end > max_end {
+                max_end = end;
+                max_end_idx = idx;
+            }
+        }
+        overlapping
+    }
+
+    /// Reads and validates a single chunk's payload at sector `offset`
+    /// within an already-open region file.
+    fn scan_chunk(f: &mut File, offset: u32, expected_x: i64, expected_z: i64) -> ChunkScanResult {
+        use nbtrs::Taglike;
+
+        if f.seek(SeekFrom::Start(offset as u64 * SECTOR_BYTES)).is_err() {
+            return ChunkScanResult::BadLength;
+        }
+        let mut len_buf = [0u8; 4];
+        if f.read_exact(&mut len_buf).is_err() {
+            return ChunkScanResult::BadLength;
+        }
+        let length = ((len_buf[0] as u32) << 24) | ((len_buf[1] as u32) << 16) |
+                     ((len_buf[2] as u32) << 8) | (len_buf[3] as u32);
+        if length == 0 || length > MAX_CHUNK_LENGTH {
+            return ChunkScanResult::BadLength;
+        }
+
+        let mut compression = [0u8; 1];
+        if f.read_exact(&mut compression).is_err() {
+            return ChunkScanResult::BadLength;
+        }
+        let mut payload = vec![0u8; length as usize - 1];
+        if f.read_exact(&mut payload).is_err() {
+            return ChunkScanResult::BadLength;
+        }
+
+        let decompressed = match decompress(compression[0], &payload) {
+            Some(bytes) => bytes,
+            None => return ChunkScanResult::BadCompression,
+        };
+
+        let root = match Tag::parse(&mut &decompressed[..]) {
+            Ok((_, tag)) => tag,
+            Err(_) => return ChunkScanResult::BadNbt,
+        };
+
+        let level = root.key("Level");
+        let x = level.key("xPos").as_i32();
+        let z = level.key("zPos").as_i32();
+        let sections_ok = level.key("Sections").as_list().is_ok();
+
+        match (x, z) {
+            (Ok(x), Ok(z)) if x as i64 == expected_x && z as i64 == expected_z && sections_ok => {
+                ChunkScanResult::Ok
+            }
+            _ => ChunkScanResult::BadNbt,
+        }
+    }
+
+    /// Rewrites every region file in this regionset to reclaim unused
+    /// sectors: valid chunks are packed contiguously starting right after
+    /// the location/timestamp tables, and the file is truncated to the new,
+    /// smaller length. Entries that aren't valid (out-of-bounds or
+    /// overlapping, see `scan`) are dropped from the location table rather
+    /// than carried forward.
+    pub fn compact(&self) -> Result<(), OverviewerError> {
+        for &(rx, rz) in &self.regions {
+            try!(self.compact_region(rx, rz));
+        }
+        Ok(())
+    }
+
+    fn compact_region(&self, rx: i64, rz: i64) -> Result<(), OverviewerError> {
+        use std::fs::OpenOptions;
+        use std::io::Write;
+
+        struct Entry {
+            idx: usize,
+            old_offset: u32,
+            data: Vec<u8>,
+        }
+
+        let path = self.region_dir.join(format!("r.{}.{}.mca", rx, rz));
+        let mut f = try!(OpenOptions::new().read(true).write(true).open(&path));
+        let file_len = try!(f.metadata()).len();
+        let total_sectors = (file_len + SECTOR_BYTES - 1) / SECTOR_BYTES;
+
+        let mut header = vec![0u8; 2 * SECTOR_BYTES as usize];
+        try!(f.read_exact(&mut header));
+
+        // Flag out-of-bounds and overlapping entries the same way `scan`
+        // does, so a compacted file never silently carries forward a
+        // location entry `scan` would have reported as corrupt.
+        let mut location_entries = Vec::new();
+        for i in 0..CHUNKS_PER_REGION {
+            let e = &header[i * 4..i * 4 + 4];
+            let offset = ((e[0] as u32) << 16) | ((e[1] as u32) << 8) | (e[2] as u32);
+            let sector_count = e[3];
+            if offset == 0 && sector_count == 0 {
+                continue;
+            }
+            if offset < 2 || (offset as u64 + sector_count as u64) > total_sectors {
+                continue;
+            }
+            location_entries.push((offset, sector_count, i));
+        }
+        let overlapping = Regionset::find_overlapping(&location_entries);
+
+        // Read every valid chunk's raw bytes (length prefix + compression
+        // byte + payload) into memory up front, before anything on disk is
+        // rewritten.
+        let mut entries = Vec::new();
+        for &(offset, _sector_count, i) in &location_entries {
+            if overlapping.contains(&i) {
+                continue;
+            }
+
+            if f.seek(SeekFrom::Start(offset as u64 * SECTOR_BYTES)).is_err() {
+                continue;
+            }
+            let mut len_buf = [0u8; 4];
+            if f.read_exact(&mut len_buf).is_err() {
+                continue;
+            }
+            let length = ((len_buf[0] as u32) << 24) | ((len_buf[1] as u32) << 16) |
+                         ((len_buf[2] as u32) << 8) | (len_buf[3] as u32);
+            if length == 0 || length > MAX_CHUNK_LENGTH {
+                continue;
+            }
+            let mut rest = vec![0u8; length as usize];
+            if f.read_exact(&mut rest).is_err() {
+                continue;
+            }
+
+            let mut data = Vec::with_capacity(4 + length as usize);
+            data.extend_from_slice(&len_buf);
+            data.extend_from_slice(&rest);
+            entries.push(Entry {
+                idx: i,
+                old_offset: offset,
+                data: data,
+            });
+        }
+
+        // Repack in order of current position; a chunk whose computed
+        // target offset matches its current one is left untouched on disk.
+        entries.sort_by_key(|e| e.old_offset);
+
+        for b in header[0..CHUNKS_PER_REGION * 4].iter_mut() {
+            *b = 0;
+        }
+
+        let mut cursor: u32 = 2;
+        for entry in &entries {
+            let sectors = ((entry.data.len() as u64 + SECTOR_BYTES - 1) / SECTOR_BYTES) as u32;
+            let new_offset = cursor;
+            cursor += sectors;
+
+            if new_offset != entry.old_offset {
+                try!(f.seek(SeekFrom::Start(new_offset as u64 * SECTOR_BYTES)));
+                try!(f.write_all(&entry.data));
+            }
+
+            let loc = &mut header[entry.idx * 4..entry.idx * 4 + 4];
+            loc[0] = ((new_offset >> 16) & 0xff) as u8;
+            loc[1] = ((new_offset >> 8) & 0xff) as u8;
+            loc[2] = (new_offset & 0xff) as u8;
+            loc[3] = sectors as u8;
+        }
+
+        try!(f.seek(SeekFrom::Start(0)));
+        try!(f.write_all(&header[0..CHUNKS_PER_REGION * 4]));
+        try!(f.set_len(cursor as u64 * SECTOR_BYTES));
+
+        Ok(())
+    }
+}
+
+/// Decompresses a chunk payload according to the Minecraft region
+/// compression id byte (1 = gzip, 2 = zlib, 3 = uncompressed). Returns
+/// `None` for an unknown id or a payload that fails to decompress.
+fn decompress(compression: u8, payload: &[u8]) -> Option<Vec<u8>> {
+    use flate2::read::{GzDecoder, ZlibDecoder};
+
+    let mut out = Vec::new();
+    match compression {
+        1 => {
+            match GzDecoder::new(payload) {
+                Ok(mut decoder) => decoder.read_to_end(&mut out).ok().map(|_| out),
+                Err(_) => None,
+            }
+        }
+        2 => {
+            let mut decoder = ZlibDecoder::new(payload);
+            decoder.read_to_end(&mut out).ok().map(|_| out)
+        }
+        3 => Some(payload.to_vec()),
+        _ => None,
+    }
 }
 
 #[derive(Debug)]
 pub struct Chunk(Tag);
-pub struct ChunkIter;
+
+impl Chunk {
+    /// Returns this chunk's own position, as recorded in its
+    /// `Level.xPos`/`Level.zPos` tags. Returns `None` rather than panicking
+    /// if those tags are missing or of the wrong type.
+    pub fn pos(&self) -> Option<Coord<coords::Chunk, coords::World>> {
+        use nbtrs::Taglike;
+
+        let level = self.0.key("Level");
+        let x = level.key("xPos").as_i32();
+        let z = level.key("zPos").as_i32();
+        match (x, z) {
+            (Ok(x), Ok(z)) => Some(Coord::new(x as i64, 0, z as i64)),
+            _ => None,
+        }
+    }
+
+    /// Returns this chunk's `Level.Sections` list, or `None` if it's
+    /// missing or isn't actually a list.
+    pub fn sections(&self) -> Option<Vec<Tag>> {
+        use nbtrs::Taglike;
+
+        self.0.key("Level").key("Sections").as_list().ok()
+    }
+
+    /// Returns the Minecraft data version this chunk was last saved with,
+    /// read from `DataVersion`. Absent on chunks saved before Minecraft 1.9.
+    pub fn data_version(&self) -> Option<i32> {
+        use nbtrs::Taglike;
+
+        self.0.key("DataVersion").as_i32().ok()
+    }
+}
+
+/// Lazily walks every `r.X.Z.mca` file in a `Regionset`'s `region_dir`,
+/// yielding `(x, z, mtime)` for each populated chunk slot. Region files are
+/// opened one at a time as the iterator advances, so this does not hold the
+/// whole regionset's chunk data in memory at once.
+pub struct ChunkIter {
+    region_dir: PathBuf,
+    regions: ::std::vec::IntoIter<(i64, i64)>,
+    current: Option<RegionLocationIter>,
+}
 
 impl Iterator for ChunkIter {
-    type Item = Chunk;
-    fn next(&mut self) -> Option<Chunk> {
-        unimplemented!()
+    type Item = (i64, i64, u32);
+    fn next(&mut self) -> Option<(i64, i64, u32)> {
+        loop {
+            if let Some(ref mut current) = self.current {
+                if let Some(item) = current.next() {
+                    return Some(item);
+                }
+            }
+            match self.regions.next() {
+                Some((x, z)) => self.current = RegionLocationIter::open(&self.region_dir, x, z),
+                None => return None,
+            }
+        }
+    }
+}
+
+/// Walks the 1024-entry location/timestamp header of a single region file,
+/// yielding `(x, z, mtime)` in chunk-space (i.e. region coordinates scaled
+/// up by the 32x32 chunks a region holds) for each populated entry.
+struct RegionLocationIter {
+    region_x: i64,
+    region_z: i64,
+    header: Vec<u8>,
+    idx: usize,
+}
+
+impl RegionLocationIter {
+    /// Reads the 8 KiB location + timestamp header from the region file at
+    /// `region_dir/r.<region_x>.<region_z>.mca`, if it exists and is
+    /// readable.
+    fn open(region_dir: &Path, region_x: i64, region_z: i64) -> Option<RegionLocationIter> {
+        let path = region_dir.join(format!("r.{}.{}.mca", region_x, region_z));
+        let mut f = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => return None,
+        };
+        let mut header = vec![0u8; 2 * SECTOR_BYTES as usize];
+        if f.read_exact(&mut header).is_err() {
+            return None;
+        }
+        Some(RegionLocationIter {
+            region_x: region_x,
+            region_z: region_z,
+            header: header,
+            idx: 0,
+        })
+    }
+}
+
+impl Iterator for RegionLocationIter {
+    type Item = (i64, i64, u32);
+    fn next(&mut self) -> Option<(i64, i64, u32)> {
+        while self.idx < CHUNKS_PER_REGION {
+            let i = self.idx;
+            self.idx += 1;
+
+            let entry = &self.header[i * 4..i * 4 + 4];
+            let offset = ((entry[0] as u32) << 16) | ((entry[1] as u32) << 8) | (entry[2] as u32);
+            let sector_count = entry[3];
+            if offset == 0 && sector_count == 0 {
+                continue;
+            }
+
+            let ts_offset = CHUNKS_PER_REGION * 4 + i * 4;
+            let ts = &self.header[ts_offset..ts_offset + 4];
+            let mtime = ((ts[0] as u32) << 24) | ((ts[1] as u32) << 16) | ((ts[2] as u32) << 8) |
+                        (ts[3] as u32);
+
+            let local_x = (i % 32) as i64;
+            let local_z = (i / 32) as i64;
+            let x = self.region_x * 32 + local_x;
+            let z = self.region_z * 32 + local_z;
+            return Some((x, z, mtime));
+        }
+        None
     }
 }
 
@@ -228,4 +946,393 @@ mod test {
         assert_eq!(rset.get_chunk_mtime(Coord::new(12, 0, 3)), Some(1454033798));
     }
 
+    #[test]
+    fn test_get_chunks() {
+        let rset = Regionset::new("tests/data/OTD/world_189/region").unwrap();
+        let chunks: Vec<(i64, i64, u32)> = rset.get_chunks().collect();
+        assert!(!chunks.is_empty());
+        assert!(chunks.contains(&(4, 8, 1454034069)));
+        assert!(chunks.contains(&(12, 3, 1454033798)));
+    }
+
+    #[test]
+    fn test_scan_fixture_is_clean() {
+        let rset = Regionset::new("tests/data/OTD/world_189/region").unwrap();
+        let stats = rset.scan();
+        assert!(stats.ok > 0);
+        assert_eq!(stats.bad_offset, 0);
+        assert_eq!(stats.overlapping, 0);
+        assert_eq!(stats.bad_length, 0);
+        assert_eq!(stats.bad_compression, 0);
+        assert_eq!(stats.bad_nbt, 0);
+    }
+
+    #[test]
+    fn test_scan_flags_bad_offset() {
+        use std::fs;
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join("overviewer_test_scan_bad_offset");
+        fs::create_dir_all(&dir).unwrap();
+        let dst = dir.join("r.0.0.mca");
+        fs::copy("tests/data/OTD/world_189/region/r.0.0.mca", &dst).unwrap();
+
+        // Chunk (0,0) lives in slot 0; stomp its location entry with an
+        // offset that runs well past the end of the file.
+        let mut f = fs::OpenOptions::new().write(true).open(&dst).unwrap();
+        f.write_all(&[0xff, 0xff, 0xff, 0xff]).unwrap();
+        drop(f);
+
+        let rset = Regionset::new(&dir).unwrap();
+        let stats = rset.scan();
+        assert_eq!(stats.bad_offset, 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_overlapping_catches_non_adjacent_overlap() {
+        // A=(offset 2, count 5) -> sectors [2,7)
+        // B=(offset 3, count 1) -> sectors [3,4)
+        // C=(offset 6, count 3) -> sectors [6,9)
+        // Sorted by offset this is A, B, C. A naive adjacent-pair comparison
+        // checks (A,B) and (B,C) but never (A,C) directly, so it misses that
+        // A and C overlap at sector 6.
+        let entries = vec![(2u32, 5u8, 0usize), (3u32, 1u8, 1usize), (6u32, 3u8, 2usize)];
+        let overlapping = Regionset::find_overlapping(&entries);
+        assert!(overlapping.contains(&0));
+        assert!(overlapping.contains(&1));
+        assert!(overlapping.contains(&2));
+    }
+
+    #[test]
+    fn test_scan_flags_overlapping() {
+        use std::fs;
+        use std::io::{Read, Write, Seek, SeekFrom};
+
+        let dir = std::env::temp_dir().join("overviewer_test_scan_overlapping");
+        fs::create_dir_all(&dir).unwrap();
+        let dst = dir.join("r.0.0.mca");
+        fs::copy("tests/data/OTD/world_189/region/r.0.0.mca", &dst).unwrap();
+
+        // Chunk (0,0) lives in slot 0, chunk (4,8) in slot 8*32+4=264.
+        // Overwrite (4,8)'s location entry with (0,0)'s so the two slots
+        // claim the exact same sectors.
+        let mut f = fs::OpenOptions::new().read(true).write(true).open(&dst).unwrap();
+        let mut slot0 = [0u8; 4];
+        f.read_exact(&mut slot0).unwrap();
+        f.seek(SeekFrom::Start(264 * 4)).unwrap();
+        f.write_all(&slot0).unwrap();
+        drop(f);
+
+        let rset = Regionset::new(&dir).unwrap();
+        let stats = rset.scan();
+        assert_eq!(stats.overlapping, 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_flags_bad_length() {
+        use std::fs;
+        use std::io::{Read, Write, Seek, SeekFrom};
+
+        let dir = std::env::temp_dir().join("overviewer_test_scan_bad_length");
+        fs::create_dir_all(&dir).unwrap();
+        let dst = dir.join("r.0.0.mca");
+        fs::copy("tests/data/OTD/world_189/region/r.0.0.mca", &dst).unwrap();
+
+        // Chunk (0,0) lives in slot 0; zero out its declared payload length.
+        let mut f = fs::OpenOptions::new().read(true).write(true).open(&dst).unwrap();
+        let mut slot0 = [0u8; 4];
+        f.read_exact(&mut slot0).unwrap();
+        let offset = ((slot0[0] as u32) << 16) | ((slot0[1] as u32) << 8) | (slot0[2] as u32);
+        f.seek(SeekFrom::Start(offset as u64 * SECTOR_BYTES)).unwrap();
+        f.write_all(&[0, 0, 0, 0]).unwrap();
+        drop(f);
+
+        let rset = Regionset::new(&dir).unwrap();
+        let stats = rset.scan();
+        assert_eq!(stats.bad_length, 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_flags_bad_compression() {
+        use std::fs;
+        use std::io::{Read, Write, Seek, SeekFrom};
+
+        let dir = std::env::temp_dir().join("overviewer_test_scan_bad_compression");
+        fs::create_dir_all(&dir).unwrap();
+        let dst = dir.join("r.0.0.mca");
+        fs::copy("tests/data/OTD/world_189/region/r.0.0.mca", &dst).unwrap();
+
+        // Chunk (0,0) lives in slot 0; declare a compression id that isn't
+        // gzip/zlib/uncompressed.
+        let mut f = fs::OpenOptions::new().read(true).write(true).open(&dst).unwrap();
+        let mut slot0 = [0u8; 4];
+        f.read_exact(&mut slot0).unwrap();
+        let offset = ((slot0[0] as u32) << 16) | ((slot0[1] as u32) << 8) | (slot0[2] as u32);
+        f.seek(SeekFrom::Start(offset as u64 * SECTOR_BYTES + 4)).unwrap();
+        f.write_all(&[9u8]).unwrap();
+        drop(f);
+
+        let rset = Regionset::new(&dir).unwrap();
+        let stats = rset.scan();
+        assert_eq!(stats.bad_compression, 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_flags_bad_nbt() {
+        use std::fs;
+        use std::io::{Read, Write, Seek, SeekFrom};
+
+        let dir = std::env::temp_dir().join("overviewer_test_scan_bad_nbt");
+        fs::create_dir_all(&dir).unwrap();
+        let dst = dir.join("r.0.0.mca");
+        fs::copy("tests/data/OTD/world_189/region/r.0.0.mca", &dst).unwrap();
+
+        // Chunk (4,8) lives in slot 8*32+4=264; overwrite its payload in
+        // place with a tiny, well-formed but empty NBT document, so it
+        // decompresses fine but has no Level.xPos to match against.
+        let mut f = fs::OpenOptions::new().read(true).write(true).open(&dst).unwrap();
+        f.seek(SeekFrom::Start(264 * 4)).unwrap();
+        let mut entry = [0u8; 4];
+        f.read_exact(&mut entry).unwrap();
+        let offset = ((entry[0] as u32) << 16) | ((entry[1] as u32) << 8) | (entry[2] as u32);
+
+        let payload = [0x0au8, 0x00, 0x00, 0x00]; // empty, unnamed TAG_Compound
+        let length = payload.len() as u32 + 1;
+        f.seek(SeekFrom::Start(offset as u64 * SECTOR_BYTES)).unwrap();
+        f.write_all(&[(length >> 24) as u8, (length >> 16) as u8, (length >> 8) as u8,
+                      length as u8])
+            .unwrap();
+        f.write_all(&[3u8]).unwrap(); // uncompressed
+        f.write_all(&payload).unwrap();
+        drop(f);
+
+        let rset = Regionset::new(&dir).unwrap();
+        let stats = rset.scan();
+        assert_eq!(stats.bad_nbt, 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fix_compression_repairs_and_rewrites_byte() {
+        use std::fs;
+        use std::io::{Read, Seek, SeekFrom, Write};
+
+        let dir = std::env::temp_dir().join("overviewer_test_fix_compression");
+        fs::create_dir_all(&dir).unwrap();
+        let dst = dir.join("r.0.0.mca");
+        fs::copy("tests/data/OTD/world_189/region/r.0.0.mca", &dst).unwrap();
+
+        // Chunk (0,0) lives in slot 0; find where its payload starts and
+        // mis-declare its compression byte as "uncompressed" (3), which is
+        // never what Anvil actually writes.
+        let mut f = fs::OpenOptions::new().read(true).write(true).open(&dst).unwrap();
+        let mut header = [0u8; 4];
+        f.read_exact(&mut header).unwrap();
+        let offset = ((header[0] as u32) << 16) | ((header[1] as u32) << 8) | (header[2] as u32);
+        let payload_start = offset as u64 * SECTOR_BYTES + 4;
+        f.seek(SeekFrom::Start(payload_start)).unwrap();
+        f.write_all(&[3u8]).unwrap();
+        drop(f);
+
+        let rset = Regionset::new(&dir).unwrap().with_fix_compression(true);
+        assert!(rset.get_chunk(Coord::new(0, 0, 0)).is_some());
+
+        // The repair should have rewritten the on-disk byte back to
+        // something else, so a plain regionset (no repair) can now load it.
+        let rset_plain = Regionset::new(&dir).unwrap();
+        assert!(rset_plain.get_chunk(Coord::new(0, 0, 0)).is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_compact_shrinks_gap_and_preserves_chunks() {
+        use std::fs::{self, OpenOptions};
+        use std::io::Write;
+        use nbtrs::Taglike;
+
+        let dir = std::env::temp_dir().join("overviewer_test_compact");
+        fs::create_dir_all(&dir).unwrap();
+        let dst = dir.join("r.0.0.mca");
+        fs::copy("tests/data/OTD/world_189/region/r.0.0.mca", &dst).unwrap();
+
+        // Pad the file with trailing sectors no location entry points at,
+        // simulating the kind of gap compaction is meant to reclaim.
+        let len_before = fs::metadata(&dst).unwrap().len();
+        {
+            let mut f = OpenOptions::new().append(true).open(&dst).unwrap();
+            f.write_all(&[0u8; 3 * 4096]).unwrap();
+        }
+        let len_padded = fs::metadata(&dst).unwrap().len();
+        assert!(len_padded > len_before);
+
+        let rset = Regionset::new(&dir).unwrap();
+        rset.compact().unwrap();
+
+        let len_after = fs::metadata(&dst).unwrap().len();
+        assert!(len_after < len_padded);
+
+        let Chunk(chunk) = rset.get_chunk(Coord::new(0, 0, 0)).unwrap();
+        assert_eq!(chunk.key("Level").key("xPos").as_i32().unwrap(), 0);
+        assert_eq!(chunk.key("Level").key("zPos").as_i32().unwrap(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_compact_drops_corrupt_entry() {
+        use std::fs::{self, OpenOptions};
+        use std::io::{Read, Write, Seek, SeekFrom};
+        use nbtrs::Taglike;
+
+        let dir = std::env::temp_dir().join("overviewer_test_compact_corrupt");
+        fs::create_dir_all(&dir).unwrap();
+        let dst = dir.join("r.0.0.mca");
+        fs::copy("tests/data/OTD/world_189/region/r.0.0.mca", &dst).unwrap();
+
+        // Chunk (4,8) lives in slot 8*32+4=264; stomp its location entry
+        // with an offset that runs off the end of the file, the same
+        // corruption scan() classifies as bad_offset.
+        {
+            let mut f = OpenOptions::new().read(true).write(true).open(&dst).unwrap();
+            f.seek(SeekFrom::Start(264 * 4)).unwrap();
+            f.write_all(&[0xff, 0xff, 0xff, 0xff]).unwrap();
+        }
+
+        let len_before = fs::metadata(&dst).unwrap().len();
+        let rset = Regionset::new(&dir).unwrap();
+        rset.compact().unwrap();
+        let len_after = fs::metadata(&dst).unwrap().len();
+
+        // The corrupt entry is dropped rather than carried forward...
+        assert!(rset.get_chunk(Coord::new(4, 0, 8)).is_none());
+        // ...while the untouched chunk (0,0) still loads with its original
+        // data intact...
+        let Chunk(chunk) = rset.get_chunk(Coord::new(0, 0, 0)).unwrap();
+        assert_eq!(chunk.key("Level").key("xPos").as_i32().unwrap(), 0);
+        assert_eq!(chunk.key("Level").key("zPos").as_i32().unwrap(), 0);
+        // ...and the file no longer reserves sectors for the dropped chunk.
+        assert!(len_after < len_before);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_parallel_matches_serial() {
+        let rset = Regionset::new("tests/data/OTD/world_189/region").unwrap();
+        assert_eq!(rset.scan(), rset.scan_parallel(4, None));
+    }
+
+    #[test]
+    fn test_par_chunks_matches_serial() {
+        let rset = Regionset::new("tests/data/OTD/world_189/region").unwrap();
+        let mut serial: Vec<(i64, i64, u32)> = rset.get_chunks().collect();
+        let mut parallel = rset.par_chunks(4);
+        serial.sort();
+        parallel.sort();
+        assert_eq!(serial, parallel);
+    }
+
+    /// Builds the bytes of a single named NBT tag: id byte, 2-byte
+    /// big-endian name length (names here always fit in one byte), the
+    /// name, then the already-encoded payload.
+    fn named_tag(id: u8, name: &str, payload: Vec<u8>) -> Vec<u8> {
+        let mut v = vec![id, 0u8, name.len() as u8];
+        v.extend_from_slice(name.as_bytes());
+        v.extend_from_slice(&payload);
+        v
+    }
+
+    fn i32_be(n: i32) -> Vec<u8> {
+        let u = n as u32;
+        vec![(u >> 24) as u8, (u >> 16) as u8, (u >> 8) as u8, u as u8]
+    }
+
+    fn i64_be(n: i64) -> Vec<u8> {
+        let u = n as u64;
+        (0..8).map(|i| (u >> ((7 - i) * 8)) as u8).collect()
+    }
+
+    fn string_payload(s: &str) -> Vec<u8> {
+        let bytes = s.as_bytes();
+        let mut v = vec![0u8, bytes.len() as u8];
+        v.extend_from_slice(bytes);
+        v
+    }
+
+    #[test]
+    fn test_world_level_dat_accessors() {
+        use std::fs;
+        use std::io::Write;
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+
+        let mut data = Vec::new();
+        data.extend(named_tag(3, "SpawnX", i32_be(10)));
+        data.extend(named_tag(3, "SpawnY", i32_be(64)));
+        data.extend(named_tag(3, "SpawnZ", i32_be(-20)));
+        data.extend(named_tag(3, "DataVersion", i32_be(169)));
+        data.extend(named_tag(4, "RandomSeed", i64_be(123456789)));
+        data.extend(named_tag(8, "LevelName", string_payload("Test World")));
+        data.push(0); // TAG_End for Data
+
+        let mut nbt = vec![10u8, 0, 0]; // root TAG_Compound, unnamed
+        nbt.extend(named_tag(10, "Data", data));
+        nbt.push(0); // TAG_End for root
+
+        let dir = std::env::temp_dir().join("overviewer_test_world_level_dat");
+        fs::create_dir_all(&dir).unwrap();
+        {
+            let f = fs::File::create(dir.join("level.dat")).unwrap();
+            let mut encoder = GzEncoder::new(f, Compression::default());
+            encoder.write_all(&nbt).unwrap();
+            encoder.finish().unwrap();
+        }
+
+        let world = World::new(&dir).unwrap();
+        assert_eq!(world.spawn(), Some((10, 64, -20)));
+        assert_eq!(world.level_name(), Some("Test World".to_string()));
+        assert_eq!(world.data_version(), Some(169));
+        assert_eq!(world.seed(), Some(123456789));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_chunk_accessors() {
+        use nbtrs::Taglike;
+
+        // Empty TAG_List payload: element type (TAG_End) + 0-length.
+        let sections = vec![0u8, 0, 0, 0, 0];
+
+        let mut level = Vec::new();
+        level.extend(named_tag(3, "xPos", i32_be(4)));
+        level.extend(named_tag(3, "zPos", i32_be(8)));
+        level.extend(named_tag(9, "Sections", sections));
+        level.push(0); // TAG_End for Level
+
+        let mut root = vec![10u8, 0, 0]; // root TAG_Compound, unnamed
+        root.extend(named_tag(10, "Level", level));
+        root.extend(named_tag(3, "DataVersion", i32_be(169)));
+        root.push(0); // TAG_End for root
+
+        let (_, tag) = Tag::parse(&mut &root[..]).unwrap();
+        let chunk = Chunk(tag);
+
+        let pos = chunk.pos().unwrap();
+        assert_eq!(pos.x, 4);
+        assert_eq!(pos.z, 8);
+        assert!(chunk.sections().unwrap().is_empty());
+        assert_eq!(chunk.data_version(), Some(169));
+    }
+
 }